@@ -0,0 +1,91 @@
+//! Daisy-chained MCP42XXX device support
+
+use crate::{interface, Command, Error, Mcp4xChain};
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")),),
+    async(feature = "async"),
+    keep_self
+)]
+impl<DI, E, const N: usize> Mcp4xChain<DI, N>
+where
+    DI: interface::WriteFrames<Error = Error<E>>,
+{
+    /// Commit `commands`, one per device ordered nearest-the-MCU first, as a
+    /// single combined SPI transaction so every device in the chain latches
+    /// its new wiper value at the same time.
+    ///
+    /// Devices that should keep their current state can be given
+    /// `Command::nop()`.
+    pub async fn commit(&mut self, commands: [Command; N]) -> Result<(), Error<E>> {
+        self.iface.write_frames(chain_frames(commands)).await
+    }
+}
+
+/// Turn device-ordered `commands` into the byte frames to shift out, nearest
+/// device last.
+///
+/// Chained devices form one long shift register: the device furthest from
+/// the MCU must be clocked in first so that, once the whole chain has been
+/// shifted, every device ends up holding the command meant for it.
+fn chain_frames<const N: usize>(commands: [Command; N]) -> [[u8; 2]; N] {
+    let mut frames = [[0u8; 2]; N];
+    for (frame, command) in frames.iter_mut().zip(commands.iter()) {
+        *frame = [command.get_command_byte(), command.get_data_byte()];
+    }
+    frames.reverse();
+    frames
+}
+
+impl<SPI, const N: usize> Mcp4xChain<interface::SpiInterface<SPI>, N> {
+    /// Create a new daisy-chain driver for `N` MCP42XXX devices sharing a
+    /// single `SpiDevice`, which manages chip-select for the combined
+    /// transaction itself.
+    pub fn new(spi: SPI) -> Self {
+        Mcp4xChain {
+            iface: interface::SpiInterface { spi },
+        }
+    }
+
+    /// Destroy driver instance, return SPI device instance.
+    pub fn destroy(self) -> SPI {
+        self.iface.spi
+    }
+}
+
+impl<SPI, CS, const N: usize> Mcp4xChain<interface::SpiBusInterface<SPI, CS>, N> {
+    /// Create a new daisy-chain driver for `N` MCP42XXX devices sharing a
+    /// raw SPI bus and a chip-select pin that this driver drives itself,
+    /// e.g. when the bus is also shared with other peripherals.
+    pub fn new_with_bus_and_cs(spi: SPI, cs: CS) -> Self {
+        Mcp4xChain {
+            iface: interface::SpiBusInterface { spi, cs },
+        }
+    }
+
+    /// Destroy driver instance, return the SPI bus and chip-select pin.
+    pub fn destroy_with_bus_and_cs(self) -> (SPI, CS) {
+        (self.iface.spi, self.iface.cs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Channel;
+
+    #[test]
+    fn chain_frames_clocks_furthest_device_first() {
+        let commands = [
+            Command::set_position(Channel::Ch0, 1),
+            Command::set_position(Channel::Ch0, 2),
+            Command::set_position(Channel::Ch0, 3),
+        ];
+        let expected = [
+            [0b0001_0001, 3],
+            [0b0001_0001, 2],
+            [0b0001_0001, 1],
+        ];
+        assert_eq!(expected, chain_frames(commands));
+    }
+}
@@ -1,15 +1,25 @@
 //! Device implementation
 
-use crate::{ic, interface, private, Channel, Command, Error, Mcp4x};
+use crate::{ic, interface, private, Channel, ChannelState, Command, Error, Mcp4x, SlaveAddr};
 use core::marker::PhantomData;
 
+#[cfg(not(feature = "async"))]
+use embedded_hal::i2c::I2c;
+#[cfg(feature = "async")]
+use embedded_hal_async::i2c::I2c;
+
 #[doc(hidden)]
 pub trait CheckParameters<CommE>: private::Sealed {
+    /// Highest valid wiper position for this IC.
+    const MAX_POSITION: u8;
+
     fn check_if_channel_is_appropriate(channel: Channel) -> Result<(), Error<CommE>>;
     fn check_if_position_is_appropriate(position: u8) -> Result<(), Error<CommE>>;
 }
 
 impl<CommE> CheckParameters<CommE> for ic::Mcp401x {
+    const MAX_POSITION: u8 = 127;
+
     fn check_if_channel_is_appropriate(channel: Channel) -> Result<(), Error<CommE>> {
         if channel == Channel::Ch0 || channel == Channel::All {
             Ok(())
@@ -19,7 +29,7 @@ impl<CommE> CheckParameters<CommE> for ic::Mcp401x {
     }
 
     fn check_if_position_is_appropriate(position: u8) -> Result<(), Error<CommE>> {
-        if position <= 127 {
+        if position <= <Self as CheckParameters<CommE>>::MAX_POSITION {
             Ok(())
         } else {
             Err(Error::OutOfBounds)
@@ -28,6 +38,8 @@ impl<CommE> CheckParameters<CommE> for ic::Mcp401x {
 }
 
 impl<CommE> CheckParameters<CommE> for ic::Mcp41x {
+    const MAX_POSITION: u8 = 255;
+
     fn check_if_channel_is_appropriate(channel: Channel) -> Result<(), Error<CommE>> {
         if channel == Channel::Ch0 || channel == Channel::All {
             Ok(())
@@ -42,6 +54,8 @@ impl<CommE> CheckParameters<CommE> for ic::Mcp41x {
 }
 
 impl<CommE> CheckParameters<CommE> for ic::Mcp42x {
+    const MAX_POSITION: u8 = 255;
+
     fn check_if_channel_is_appropriate(_: Channel) -> Result<(), Error<CommE>> {
         Ok(())
     }
@@ -70,26 +84,163 @@ where
         IC::check_if_position_is_appropriate(position)?;
         self.iface
             .write_command(Command::SetPosition(channel, position))
-            .await
+            .await?;
+        self.update_state(channel, |state| match state {
+            ChannelState::Active(_) => ChannelState::Active(position),
+            ChannelState::Shutdown(_) => ChannelState::Shutdown(position),
+        });
+        Ok(())
     }
 
     /// Shutdown a channel.
     ///
     /// Will return `Error::WrongChannel` if the channel provided is not available
-    /// on the device.
+    /// on the device. The channel moves to [`ChannelState::Shutdown`]; its
+    /// staged position is kept and can still be changed with `set_position`,
+    /// but its "A" terminal stays disconnected until `wake` is called.
     pub async fn shutdown(&mut self, channel: Channel) -> Result<(), Error<CommE>> {
         IC::check_if_channel_is_appropriate(channel)?;
-        self.iface.write_command(Command::Shutdown(channel)).await
+        self.iface.write_command(Command::Shutdown(channel)).await?;
+        self.update_state(channel, |state| ChannelState::Shutdown(state.position()));
+        Ok(())
+    }
+
+    /// Re-enable a shut down channel's "A" terminal, moving it back to
+    /// [`ChannelState::Active`]. Re-sends the position already staged for
+    /// the channel, so the caller does not need to supply one.
+    ///
+    /// Will return `Error::WrongChannel` if the channel provided is not
+    /// available on the device, or if `Channel::All` is given, since waking
+    /// more than one channel at once could require sending two different
+    /// staged positions.
+    pub async fn wake(&mut self, channel: Channel) -> Result<(), Error<CommE>> {
+        IC::check_if_channel_is_appropriate(channel)?;
+        let index = channel.index().ok_or(Error::WrongChannel)?;
+        let position = self.states[index].position();
+        self.iface
+            .write_command(Command::SetPosition(channel, position))
+            .await?;
+        self.states[index] = ChannelState::Active(position);
+        Ok(())
+    }
+
+    /// Return the last position written to `channel`, whether it is active
+    /// or staged while shut down. Returns `None` for `Channel::All`, which
+    /// does not identify a single channel.
+    pub fn position(&self, channel: Channel) -> Option<u8> {
+        channel.index().map(|index| self.states[index].position())
+    }
+
+    /// Return `channel`'s current lifecycle state. Returns `None` for
+    /// `Channel::All`, which does not identify a single channel.
+    pub fn channel_state(&self, channel: Channel) -> Option<ChannelState> {
+        channel.index().map(|index| self.states[index])
+    }
+
+    /// Increase `channel`'s position by `by`, saturating at the device's
+    /// maximum position.
+    ///
+    /// Will return `Error::WrongChannel` if `channel` is `Channel::All`.
+    pub async fn increment(&mut self, channel: Channel, by: u8) -> Result<(), Error<CommE>> {
+        let current = self.position(channel).ok_or(Error::WrongChannel)?;
+        self.set_position(channel, saturating_increment(current, by, IC::MAX_POSITION))
+            .await
+    }
+
+    /// Decrease `channel`'s position by `by`, saturating at zero.
+    ///
+    /// Will return `Error::WrongChannel` if `channel` is `Channel::All`.
+    pub async fn decrement(&mut self, channel: Channel, by: u8) -> Result<(), Error<CommE>> {
+        let current = self.position(channel).ok_or(Error::WrongChannel)?;
+        self.set_position(channel, saturating_decrement(current, by))
+            .await
     }
 }
 
-impl<I2C> Mcp4x<interface::I2cInterface<I2C>, ic::Mcp401x> {
-    /// Create new MCP401x device instance
-    pub fn new_mcp401x(i2c: I2C) -> Self {
-        Mcp4x {
-            iface: interface::I2cInterface { i2c },
-            _ic: PhantomData,
+impl<DI, IC> Mcp4x<DI, IC> {
+    fn update_state(&mut self, channel: Channel, mut f: impl FnMut(ChannelState) -> ChannelState) {
+        match channel.index() {
+            Some(index) => self.states[index] = f(self.states[index]),
+            None => {
+                self.states[0] = f(self.states[0]);
+                self.states[1] = f(self.states[1]);
+            }
+        }
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")),),
+    async(feature = "async"),
+    keep_self
+)]
+impl<DI, IC, CommE> Mcp4x<DI, IC>
+where
+    DI: interface::WriteFrames<Error = Error<CommE>>,
+    IC: CheckParameters<CommE>,
+{
+    /// Write a precomputed batch of commands as a single SPI transaction.
+    ///
+    /// Every command is validated via the usual parameter checks before
+    /// anything is written, so a failing entry leaves the device state
+    /// exactly as it was before the call. This lets callers atomically
+    /// reprogram both channels of an MCP42xxx, or replay a precomputed
+    /// command without rebuilding it each time.
+    pub async fn write_commands<const N: usize>(
+        &mut self,
+        commands: [Command; N],
+    ) -> Result<(), Error<CommE>> {
+        for command in &commands {
+            match *command {
+                Command::SetPosition(channel, position) => {
+                    IC::check_if_channel_is_appropriate(channel)?;
+                    IC::check_if_position_is_appropriate(position)?;
+                }
+                Command::Shutdown(channel) => {
+                    IC::check_if_channel_is_appropriate(channel)?;
+                }
+                Command::Nop => {}
+            }
         }
+        let mut frames = [[0u8; 2]; N];
+        for (frame, command) in frames.iter_mut().zip(commands.iter()) {
+            *frame = [command.get_command_byte(), command.get_data_byte()];
+        }
+        self.iface.write_frames(frames).await?;
+        for command in &commands {
+            match *command {
+                Command::SetPosition(channel, position) => {
+                    self.update_state(channel, |state| match state {
+                        ChannelState::Active(_) => ChannelState::Active(position),
+                        ChannelState::Shutdown(_) => ChannelState::Shutdown(position),
+                    });
+                }
+                Command::Shutdown(channel) => {
+                    self.update_state(channel, |state| ChannelState::Shutdown(state.position()));
+                }
+                Command::Nop => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<I2C, E> Mcp4x<interface::I2cInterface<I2C>, ic::Mcp401x>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Create new MCP401x device instance.
+    ///
+    /// Returns `Error::AddressOutOfRange` if `address` does not fit in 7
+    /// bits, or `Error::AddressReserved` if it falls in one of the I2C
+    /// reserved address ranges (`0x00..=0x07` or `0x78..=0x7F`).
+    pub fn new_mcp401x(i2c: I2C, address: SlaveAddr) -> Result<Self, Error<E>> {
+        let address = validate_address(address.addr())?;
+        Ok(Mcp4x {
+            iface: interface::I2cInterface { i2c, address },
+            states: [ChannelState::default(); 2],
+            _ic: PhantomData,
+        })
     }
 
     /// Destroy driver instance, return I2C bus instance.
@@ -98,16 +249,35 @@ impl<I2C> Mcp4x<interface::I2cInterface<I2C>, ic::Mcp401x> {
     }
 }
 
+fn saturating_increment(current: u8, by: u8, max_position: u8) -> u8 {
+    current.saturating_add(by).min(max_position)
+}
+
+fn saturating_decrement(current: u8, by: u8) -> u8 {
+    current.saturating_sub(by)
+}
+
+fn validate_address<E>(address: u8) -> Result<u8, Error<E>> {
+    if address > 0x7F {
+        return Err(Error::AddressOutOfRange(address));
+    }
+    if (0x00..=0x07).contains(&address) || (0x78..=0x7F).contains(&address) {
+        return Err(Error::AddressReserved(address));
+    }
+    Ok(address)
+}
+
 impl<SPI> Mcp4x<interface::SpiInterface<SPI>, ic::Mcp41x> {
     /// Create new MCP41x device instance
     pub fn new_mcp41x(spi: SPI) -> Self {
         Mcp4x {
             iface: interface::SpiInterface { spi },
+            states: [ChannelState::default(); 2],
             _ic: PhantomData,
         }
     }
 
-    /// Destroy driver instance, return SPI bus instance and CS output pin.
+    /// Destroy driver instance, return SPI bus instance.
     pub fn destroy_mcp41x(self) -> SPI {
         self.iface.spi
     }
@@ -118,12 +288,81 @@ impl<SPI> Mcp4x<interface::SpiInterface<SPI>, ic::Mcp42x> {
     pub fn new_mcp42x(spi: SPI) -> Self {
         Mcp4x {
             iface: interface::SpiInterface { spi },
+            states: [ChannelState::default(); 2],
             _ic: PhantomData,
         }
     }
 
-    /// Destroy driver instance, return SPI bus instance and CS output pin.
+    /// Destroy driver instance, return SPI bus instance.
     pub fn destroy_mcp42x(self) -> SPI {
         self.iface.spi
     }
 }
+
+impl<SPI, CS> Mcp4x<interface::SpiBusInterface<SPI, CS>, ic::Mcp41x> {
+    /// Create new MCP41x device instance from a raw SPI bus and a
+    /// chip-select pin that this driver drives itself, e.g. to keep CS
+    /// asserted alongside other transfers on a shared bus.
+    pub fn new_mcp41x_with_bus_and_cs(spi: SPI, cs: CS) -> Self {
+        Mcp4x {
+            iface: interface::SpiBusInterface { spi, cs },
+            states: [ChannelState::default(); 2],
+            _ic: PhantomData,
+        }
+    }
+
+    /// Destroy driver instance, return the SPI bus and chip-select pin.
+    pub fn destroy_mcp41x_with_bus_and_cs(self) -> (SPI, CS) {
+        (self.iface.spi, self.iface.cs)
+    }
+}
+
+impl<SPI, CS> Mcp4x<interface::SpiBusInterface<SPI, CS>, ic::Mcp42x> {
+    /// Create new MCP42x device instance from a raw SPI bus and a
+    /// chip-select pin that this driver drives itself, e.g. to keep CS
+    /// asserted alongside other transfers on a shared bus.
+    pub fn new_mcp42x_with_bus_and_cs(spi: SPI, cs: CS) -> Self {
+        Mcp4x {
+            iface: interface::SpiBusInterface { spi, cs },
+            states: [ChannelState::default(); 2],
+            _ic: PhantomData,
+        }
+    }
+
+    /// Destroy driver instance, return the SPI bus and chip-select pin.
+    pub fn destroy_mcp42x_with_bus_and_cs(self) -> (SPI, CS) {
+        (self.iface.spi, self.iface.cs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! validate_address {
+        ($name:ident, $address:expr, $expected:pat) => {
+            #[test]
+            fn $name() {
+                assert!(matches!(validate_address::<()>($address), $expected));
+            }
+        };
+    }
+
+    validate_address!(address_0x07_is_reserved, 0x07, Err(Error::AddressReserved(0x07)));
+    validate_address!(address_0x08_is_allowed, 0x08, Ok(0x08));
+    validate_address!(address_0x77_is_allowed, 0x77, Ok(0x77));
+    validate_address!(address_0x78_is_reserved, 0x78, Err(Error::AddressReserved(0x78)));
+    validate_address!(address_0x7f_is_reserved, 0x7F, Err(Error::AddressReserved(0x7F)));
+    validate_address!(address_0x80_is_out_of_range, 0x80, Err(Error::AddressOutOfRange(0x80)));
+
+    #[test]
+    fn increment_saturates_at_mcp401x_max_position() {
+        let max = <ic::Mcp401x as CheckParameters<()>>::MAX_POSITION;
+        assert_eq!(127, saturating_increment(125, 10, max));
+    }
+
+    #[test]
+    fn decrement_saturates_at_zero() {
+        assert_eq!(0, saturating_decrement(3, 10));
+    }
+}
@@ -4,26 +4,43 @@
 use core::future;
 
 use crate::{private, Command, Error};
+use embedded_hal::digital::OutputPin;
 #[cfg(not(feature = "async"))]
 use embedded_hal::i2c::I2c;
 #[cfg(feature = "async")]
 use embedded_hal_async::i2c::I2c;
 
 #[cfg(not(feature = "async"))]
-use embedded_hal::spi::SpiDevice;
+use embedded_hal::spi::{Operation, SpiBus, SpiDevice};
 #[cfg(feature = "async")]
-use embedded_hal_async::spi::SpiDevice;
+use embedded_hal_async::spi::{Operation, SpiBus, SpiDevice};
 
-/// SPI interface
+/// SPI interface backed by an `embedded-hal` `SpiDevice`, which manages chip
+/// select itself for every write.
 #[derive(Debug, Default)]
 pub struct SpiInterface<SPI> {
     pub(crate) spi: SPI,
 }
 
+/// SPI interface backed by a raw `SpiBus` plus a dedicated chip-select pin
+/// that this driver drives itself.
+///
+/// Unlike [`SpiInterface`], this does not need an `SpiDevice`, e.g. to share
+/// the bus with other peripherals under a bus manager that does not produce
+/// one. Combined writes, such as latching a whole daisy-chain of devices
+/// together, keep chip-select asserted across all frames with either
+/// interface.
+#[derive(Debug, Default)]
+pub struct SpiBusInterface<SPI, CS> {
+    pub(crate) spi: SPI,
+    pub(crate) cs: CS,
+}
+
 /// I2C interface
 #[derive(Debug, Default)]
 pub struct I2cInterface<I2C> {
     pub(crate) i2c: I2C,
+    pub(crate) address: u8,
 }
 
 /// Perform a command
@@ -59,6 +76,87 @@ where
     }
 }
 
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")),),
+    async(feature = "async"),
+    keep_self
+)]
+impl<SPI, CS, E> WriteCommand for SpiBusInterface<SPI, CS>
+where
+    SPI: SpiBus<Error = E>,
+    CS: OutputPin<Error = core::convert::Infallible>,
+{
+    type Error = Error<E>;
+
+    async fn write_command(&mut self, command: Command) -> Result<(), Self::Error> {
+        let payload: [u8; 2] = [command.get_command_byte(), command.get_data_byte()];
+        self.cs.set_low().unwrap();
+        let mut result = self.spi.write(&payload).await;
+        if result.is_ok() {
+            result = self.spi.flush().await;
+        }
+        self.cs.set_high().unwrap();
+        result.map_err(Error::Comm)
+    }
+}
+
+/// Write a daisy-chain's combined per-device frames as a single transaction
+/// with chip-select held low across all of them.
+pub trait WriteFrames: private::Sealed {
+    /// Error type
+    type Error;
+
+    #[cfg(not(feature = "async"))]
+    /// Write `frames`, one `(command byte, data byte)` pair per device.
+    fn write_frames<const N: usize>(&mut self, frames: [[u8; 2]; N]) -> Result<(), Self::Error>;
+    #[cfg(feature = "async")]
+    /// Write `frames`, one `(command byte, data byte)` pair per device.
+    fn write_frames<const N: usize>(
+        &mut self,
+        frames: [[u8; 2]; N],
+    ) -> impl future::Future<Output = Result<(), Self::Error>>;
+}
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")),),
+    async(feature = "async"),
+    keep_self
+)]
+impl<SPI, E> WriteFrames for SpiInterface<SPI>
+where
+    SPI: SpiDevice<Error = E>,
+{
+    type Error = Error<E>;
+
+    async fn write_frames<const N: usize>(&mut self, frames: [[u8; 2]; N]) -> Result<(), Self::Error> {
+        let mut ops: [Operation<'_, u8>; N] = core::array::from_fn(|i| Operation::Write(&frames[i][..]));
+        self.spi.transaction(&mut ops).await.map_err(Error::Comm)
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")),),
+    async(feature = "async"),
+    keep_self
+)]
+impl<SPI, CS, E> WriteFrames for SpiBusInterface<SPI, CS>
+where
+    SPI: SpiBus<Error = E>,
+    CS: OutputPin<Error = core::convert::Infallible>,
+{
+    type Error = Error<E>;
+
+    async fn write_frames<const N: usize>(&mut self, frames: [[u8; 2]; N]) -> Result<(), Self::Error> {
+        self.cs.set_low().unwrap();
+        let mut result = self.spi.write(frames.as_flattened()).await;
+        if result.is_ok() {
+            result = self.spi.flush().await;
+        }
+        self.cs.set_high().unwrap();
+        result.map_err(Error::Comm)
+    }
+}
+
 #[maybe_async_cfg::maybe(
     sync(cfg(not(feature = "async")),),
     async(feature = "async"),
@@ -71,14 +169,13 @@ where
     type Error = Error<E>;
 
     async fn write_command(&mut self, command: Command) -> Result<(), Self::Error> {
-        const ADDRESS: u8 = 0b0101111;
         match command {
             Command::SetPosition(_, position) => self
                 .i2c
-                .write(ADDRESS, &[position])
+                .write(self.address, &[position])
                 .await
                 .map_err(Error::Comm),
-            Command::Shutdown(_) => Err(Error::Unsupported),
+            Command::Shutdown(_) | Command::Nop => Err(Error::Unsupported),
         }
     }
 }
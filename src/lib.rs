@@ -6,6 +6,7 @@
 //! This driver allows you to:
 //! - Set a channel to a position.
 //! - Shutdown a channel.
+//! - Drive a daisy-chain of devices as one combined transaction.
 //!
 //! ## The devices
 //! The MCP41XXX and MCP42XXX devices are 256-position, digital potentiometers
@@ -46,6 +47,42 @@ extern crate embedded_hal as hal;
 pub enum Error<E> {
     /// Communication error
     Comm(E),
+    /// The channel provided is not available on this device
+    WrongChannel,
+    /// The position provided is out of the device's valid range
+    OutOfBounds,
+    /// The operation is not supported by this device/interface combination
+    Unsupported,
+    /// The I2C address provided does not fit in 7 bits
+    AddressOutOfRange(u8),
+    /// The I2C address provided is in a reserved range (0x00-0x07 or 0x78-0x7F)
+    AddressReserved(u8),
+}
+
+/// Possible slave addresses for the I2C interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlaveAddr {
+    /// Default slave address
+    Default,
+    /// Custom slave address
+    Custom(u8),
+}
+
+impl Default for SlaveAddr {
+    fn default() -> Self {
+        SlaveAddr::Default
+    }
+}
+
+impl SlaveAddr {
+    const DEFAULT_ADDRESS: u8 = 0b0101_111;
+
+    fn addr(self) -> u8 {
+        match self {
+            SlaveAddr::Default => Self::DEFAULT_ADDRESS,
+            SlaveAddr::Custom(addr) => addr,
+        }
+    }
 }
 
 /// Channel selector
@@ -55,6 +92,8 @@ pub enum Channel {
     Ch0,
     /// Channel 1 (only for MCP42XXX devices)
     Ch1,
+    /// Both channels (only for MCP42XXX devices)
+    All,
 }
 
 impl Channel {
@@ -62,90 +101,151 @@ impl Channel {
         match self {
             Channel::Ch0 => 1,
             Channel::Ch1 => 2,
+            Channel::All => 3,
+        }
+    }
+
+    /// Index into the per-channel shadow state, or `None` for `Channel::All`,
+    /// which addresses both channels at once rather than a single one.
+    fn index(self) -> Option<usize> {
+        match self {
+            Channel::Ch0 => Some(0),
+            Channel::Ch1 => Some(1),
+            Channel::All => None,
         }
     }
 }
 
-enum Command {
+/// A single wiper command understood by the MCP4x command protocol.
+///
+/// The variants are public (rather than only the `set_position`/`shutdown`/
+/// `nop` constructors below) because daisy-chain and batched writes need to
+/// build and match on whole command arrays; callers are still encouraged to
+/// prefer the constructors over naming a variant directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
     /// Set a channel to a position
     SetPosition(Channel, u8),
     /// Shutdown channel
     Shutdown(Channel),
+    /// Do nothing and leave the addressed channel(s) unchanged.
+    ///
+    /// Useful to hold a device's outputs steady, e.g. when addressing a
+    /// single device within a daisy-chain of several.
+    Nop,
 }
 
 impl Command {
+    /// Build a command that sets `channel` to `position`.
+    pub fn set_position(channel: Channel, position: u8) -> Self {
+        Command::SetPosition(channel, position)
+    }
+
+    /// Build a command that shuts down `channel`.
+    pub fn shutdown(channel: Channel) -> Self {
+        Command::Shutdown(channel)
+    }
+
+    /// Build a command that leaves the addressed channel(s) unchanged.
+    pub fn nop() -> Self {
+        Command::Nop
+    }
+
     fn get_command_byte(&self) -> u8 {
         match *self {
             Command::SetPosition(channel, _) => 0b0001_0000 | channel.get_bits(),
             Command::Shutdown(channel) => 0b0010_0000 | channel.get_bits(),
+            Command::Nop => 0b0000_0000,
         }
     }
     fn get_data_byte(&self) -> u8 {
         match *self {
             Command::SetPosition(_, position) => position,
-            Command::Shutdown(_) => 0,
+            Command::Shutdown(_) | Command::Nop => 0,
         }
     }
 }
 
 /// IC markers
 pub mod ic {
+    /// MCP401x IC marker
+    pub struct Mcp401x(());
     /// MCP41x IC marker
     pub struct Mcp41x(());
+    /// MCP42x IC marker
+    pub struct Mcp42x(());
 }
 
-/// MCP4x digital potentiometer driver
-#[derive(Debug, Default)]
-pub struct Mcp4x<DI, IC> {
-    iface: DI,
-    _ic: PhantomData<IC>,
+/// Wiper position at power-up, hardware reset or RS pin assertion
+const MID_SCALE: u8 = 0x80;
+
+/// Lifecycle state of a single channel's wiper.
+///
+/// A channel starts out `Active`. `Mcp4x::shutdown` disconnects its "A"
+/// terminal and moves it to `Shutdown`; `Mcp4x::set_position` while shut
+/// down still stages the new value in the wiper register, as the datasheet
+/// allows, but leaves the terminal disconnected until `Mcp4x::wake` is
+/// called.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelState {
+    /// The channel's "A" terminal is connected and driven to `position`.
+    Active(u8),
+    /// The channel is shut down; `position` is the value staged in the
+    /// wiper register and will take effect once the channel wakes.
+    Shutdown(u8),
 }
 
-impl<DI, IC, E> Mcp4x<DI, IC>
-where
-    DI: interface::WriteCommand<Error = E>
-{
-    /// Set a channel to a position
-    pub fn set_position(&mut self, channel: Channel, position: u8) -> Result<(), Error<E>> {
-        // TODO check channel is appropriate for IC
-        let cmd = Command::SetPosition(channel, position);
-        self.iface.write_command(cmd.get_command_byte(), cmd.get_data_byte())
+impl ChannelState {
+    fn position(self) -> u8 {
+        match self {
+            ChannelState::Active(position) | ChannelState::Shutdown(position) => position,
+        }
     }
+}
 
-    /// Shutdown a channel
-    pub fn shutdown(&mut self, channel: Channel) -> Result<(), Error<E>> {
-        // TODO check channel is appropriate for IC
-        let cmd = Command::Shutdown(channel);
-        self.iface.write_command(cmd.get_command_byte(), cmd.get_data_byte())
+impl Default for ChannelState {
+    fn default() -> Self {
+        ChannelState::Active(MID_SCALE)
     }
 }
 
-impl<SPI, CS> Mcp4x<interface::SpiInterface<SPI, CS>, ic::Mcp41x> {
-    /// Create new MCP41x device instance
-    pub fn new_mcp41x(spi: SPI, chip_select: CS) -> Self {
-        Mcp4x {
-            iface: interface::SpiInterface {
-                spi,
-                cs: chip_select
-            },
-            _ic: PhantomData,
-        }
-    }
+/// MCP4x digital potentiometer driver
+#[derive(Debug, Default)]
+pub struct Mcp4x<DI, IC> {
+    iface: DI,
+    /// Lifecycle state of each channel, indexed by `Channel::index()`.
+    states: [ChannelState; 2],
+    _ic: PhantomData<IC>,
+}
 
-    /// Destroy driver instance, return SPI bus instance and CS output pin.
-    pub fn destroy_mcp41x(self) -> (SPI, CS) {
-        (self.iface.spi, self.iface.cs)
-    }
+/// Driver for a daisy-chain of `N` MCP42XXX devices sharing a single
+/// chip-select line.
+///
+/// Chained devices behave as one long shift register: addressing a single
+/// device within the chain requires a command for every device, so
+/// devices that should be left untouched can be given `Command::Nop`.
+#[derive(Debug)]
+pub struct Mcp4xChain<DI, const N: usize> {
+    iface: DI,
 }
 
 #[doc(hidden)]
 pub mod interface;
 
+mod device_impl;
+
+mod chain_impl;
+
 mod private {
-    use super::interface;
+    use super::{ic, interface};
     pub trait Sealed {}
 
-    impl<SPI, CS> Sealed for interface::SpiInterface<SPI, CS> {}
+    impl Sealed for ic::Mcp401x {}
+    impl Sealed for ic::Mcp41x {}
+    impl Sealed for ic::Mcp42x {}
+    impl<SPI> Sealed for interface::SpiInterface<SPI> {}
+    impl<SPI, CS> Sealed for interface::SpiBusInterface<SPI, CS> {}
+    impl<I2C> Sealed for interface::I2cInterface<I2C> {}
 }
 
 #[cfg(test)]
@@ -179,4 +279,11 @@ mod tests {
 
     shutdown!(can_shutdown_ch_0, Ch0, 0b0010_0001);
     shutdown!(can_shutdown_ch_1, Ch1, 0b0010_0010);
-}
\ No newline at end of file
+
+    #[test]
+    fn nop_is_all_zero_bits() {
+        let cmd = Command::Nop;
+        assert_eq!(0b0000_0000, cmd.get_command_byte());
+        assert_eq!(0, cmd.get_data_byte());
+    }
+}